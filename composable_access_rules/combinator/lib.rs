@@ -0,0 +1,125 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+//!
+//! Composable Access Rule Combinator
+//!
+//! # Goal
+//! Lets a data owner build a policy out of independent `ComposableAccessRule`
+//! contracts (e.g. limited-use, owner-only) by combining their `execute` checks
+//! with a boolean combinator, instead of writing a new bespoke rule contract
+//! for every policy.
+//!
+//! # Combinators
+//! - `And`: every child must allow the call
+//! - `Or`: any child allowing the call is enough
+//! - `Threshold(k)`: at least `k` children must allow the call
+//!
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod composable_access_rule_combinator {
+    use ink_storage::traits::SpreadAllocate;
+    use ink_prelude::vec::Vec;
+    // `traits` is an out-of-tree crate (not vendored in this repository) that
+    // defines `ComposableAccessRule`/`ComposableAccessRuleRef`. Both the local
+    // `execute` impl and the cross-calls through `ComposableAccessRuleRef` below
+    // assume `fn execute(&mut self, asset_id: u32) -> bool`; the published trait
+    // must declare the same signature in lockstep, or neither contract compiles.
+    use traits::{ComposableAccessRule, ComposableAccessRuleRef};
+
+    /// how the results of the child rules are combined into a single pass/fail
+    #[derive(Debug, Clone, Copy, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum Combinator {
+        And,
+        Or,
+        Threshold(u8),
+    }
+
+    #[ink(event)]
+    pub struct CompositeExecutionSuccessful {}
+
+    #[ink(event)]
+    pub struct CompositeExecutionFailed {
+        /// the child rule contract whose check caused the composite to fail;
+        /// `None` for an `Or` or `Threshold` combinator that simply didn't reach quorum
+        #[ink(topic)]
+        failed_child: Option<AccountId>,
+    }
+
+    #[ink(storage)]
+    #[derive(SpreadAllocate)]
+    pub struct ComposableAccessRuleCombinator {
+        /// the independent rule contracts this policy is built from
+        children: Vec<AccountId>,
+        combinator: Combinator,
+    }
+
+    impl ComposableAccessRuleCombinator {
+        #[ink(constructor)]
+        pub fn new(children: Vec<AccountId>, combinator: Combinator) -> Self {
+            if let Combinator::Threshold(k) = combinator {
+                if k == 0 || k as usize > children.len() {
+                    panic!("threshold must be between 1 and the number of children");
+                }
+            }
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.children = children;
+                contract.combinator = combinator;
+            })
+        }
+    }
+
+    impl ComposableAccessRule for ComposableAccessRuleCombinator {
+        /// composing `register` has no single sensible meaning, so it's a no-op:
+        /// each child rule contract is registered independently by its owner.
+        #[ink(message, payable)]
+        fn register(&mut self, _asset_id: u32) {}
+
+        /// cross-call every child's `execute` and combine the results according to
+        /// `combinator`, short-circuiting as soon as the outcome is determined:
+        /// - `And` fails (and stops) on the first child that disallows the call
+        /// - `Or` succeeds (and stops) on the first child that allows the call
+        /// - `Threshold(k)` stops as soon as `k` children have allowed the call, or
+        ///   as soon as quorum becomes unreachable given the children left to check
+        #[ink(message, payable)]
+        fn execute(&mut self, asset_id: u32) -> bool {
+            let required = match self.combinator {
+                Combinator::And => self.children.len() as u8,
+                Combinator::Or => 1,
+                Combinator::Threshold(k) => k,
+            };
+            let remaining_after = |checked: usize| (self.children.len() - checked) as u8;
+
+            let mut allowed: u8 = 0;
+            for (checked, child) in self.children.clone().iter().enumerate() {
+                let mut rule: ComposableAccessRuleRef =
+                    ink_env::call::FromAccountId::from_account_id(*child);
+                let ok = rule.execute(asset_id);
+
+                if ok {
+                    allowed += 1;
+                    if allowed >= required {
+                        self.env().emit_event(CompositeExecutionSuccessful {});
+                        return true;
+                    }
+                } else if matches!(self.combinator, Combinator::And) {
+                    self.env().emit_event(CompositeExecutionFailed {
+                        failed_child: Some(*child),
+                    });
+                    return false;
+                } else if allowed + remaining_after(checked + 1) < required {
+                    // not enough children left to still reach the threshold
+                    self.env().emit_event(CompositeExecutionFailed { failed_child: None });
+                    return false;
+                }
+            }
+
+            self.env().emit_event(CompositeExecutionFailed { failed_child: None });
+            false
+        }
+    }
+}
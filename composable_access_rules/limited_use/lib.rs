@@ -61,6 +61,10 @@ impl Environment for CustomEnvironment {
 #[ink::contract(env = crate::CustomEnvironment)]
 mod limited_use_rule {
     use ink_storage::traits::SpreadAllocate;
+    // `traits` is an out-of-tree crate (not vendored in this repository) that
+    // defines `ComposableAccessRule`. The `execute` impl below returns `bool`,
+    // so the published trait must declare `fn execute(&mut self, asset_id: u32) -> bool`
+    // in lockstep, or this contract won't compile against it.
     use traits::ComposableAccessRule;
 
     #[ink(event)]
@@ -127,12 +131,26 @@ mod limited_use_rule {
             }
         }
 
+        /// enforce the per-caller usage limit for a registered asset: reject (and emit
+        /// `ExecutionFailed`) once the caller has reached `limit` executions, otherwise
+        /// count this one and emit `ExecutionSuccessful`. Returns whether the call was
+        /// allowed, so combinator rule contracts can compose this with other rules.
         #[ink(message, payable)]
-        fn execute(&mut self, asset_id: u32) {
-            // let caller = self.env().caller();
-            // // get count for the asset id
-            // let access_limit = self.asset_registry.get(&asset_id);
-            // // if let Some(self.usage_counter)
+        fn execute(&mut self, asset_id: u32) -> bool {
+            let caller = self.env().caller();
+            if self.asset_registry.get(&asset_id).is_none() {
+                self.env().emit_event(ExecutionFailed{});
+                return false;
+            }
+            let count = self.usage_counter.get(&caller).unwrap_or(0);
+            if count >= self.limit {
+                self.env().emit_event(ExecutionFailed{});
+                false
+            } else {
+                self.usage_counter.insert(&caller, &(count + 1));
+                self.env().emit_event(ExecutionSuccessful{});
+                true
+            }
         }
     }
 
@@ -8,17 +8,27 @@ use ink_lang as ink;
 pub trait Iris {
     type ErrorCode = IrisErr;
 
-    #[ink(extension = 0, returns_result = false)]
+    #[ink(extension = 0, returns_result = true)]
     fn transfer_asset(caller: ink_env::AccountId, target: ink_env::AccountId, asset_id: u32, amount: u64) -> [u8; 32];
 
-    #[ink(extension = 1, returns_result = false)]
+    #[ink(extension = 1, returns_result = true)]
     fn mint(caller: ink_env::AccountId, target: ink_env::AccountId, asset_id: u32, amount: u64) -> [u8; 32];
 
-    #[ink(extension = 2, returns_result = false)]
+    #[ink(extension = 2, returns_result = true)]
     fn lock(amount: u64) -> [u8; 32];
 
-    #[ink(extension = 3, returns_result = false)]
+    #[ink(extension = 3, returns_result = true)]
     fn unlock_and_transfer(target: ink_env::AccountId) -> [u8; 32];
+
+    /// lock `amount` of currency, recording a release block height so the runtime can
+    /// reject an early `unlock_and_transfer_timelocked`
+    #[ink(extension = 4, returns_result = true)]
+    fn lock_until(amount: u64, release_block: u32) -> [u8; 32];
+
+    /// unlock and transfer currency locked by `lock_until`; the runtime rejects this
+    /// until the current block is at or past the recorded release height
+    #[ink(extension = 5, returns_result = true)]
+    fn unlock_and_transfer_timelocked(target: ink_env::AccountId) -> [u8; 32];
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
@@ -28,16 +38,38 @@ pub enum IrisErr {
     FailMintAssets,
     FailLockCurrency,
     FailUnlockCurrency,
+    FailLockUntil,
+    FailUnlockTimelocked,
+    /// `publish_sale` was called twice for the same `(caller, asset_id)`
+    DuplicateListing,
+    /// a purchase would push `units_sold` past the listing's `max_supply`
+    ExceedsMaxSupply,
+    /// the caller's transferred value didn't match `amount * price`
+    IncorrectPayment,
+    /// the runtime returned a status code this contract doesn't recognize
+    UnknownStatusCode,
+    /// only the exchange's deployer may call `terminate`
+    NotOwner,
+    /// a listing's `PriceOracle` cross-contract call didn't return a quote
+    OracleQueryFailed,
+    /// refunding the caller's attached native value back to them failed
+    RefundFailed,
 }
 
+/// converts a runtime dispatch status code into a typed `IrisErr`, the way each
+/// chain-extension call's `Result` is produced; unrecognized codes are surfaced as
+/// `UnknownStatusCode` rather than panicking, so a runtime upgrade can't brick calls.
 impl ink_env::chain_extension::FromStatusCode for IrisErr {
     fn from_status_code(status_code: u32) -> Result<(), Self> {
         match status_code {
-            0 => Err(Self::FailTransferAsset),
-            1 => Err(Self::FailMintAssets),
-            2 => Err(Self::FailLockCurrency),
-            3 => Err(Self::FailUnlockCurrency),
-            _ => panic!("encountered unknown status code"),
+            0 => Ok(()),
+            1 => Err(Self::FailTransferAsset),
+            2 => Err(Self::FailMintAssets),
+            3 => Err(Self::FailLockCurrency),
+            4 => Err(Self::FailUnlockCurrency),
+            5 => Err(Self::FailLockUntil),
+            6 => Err(Self::FailUnlockTimelocked),
+            _ => Err(Self::UnknownStatusCode),
         }
     }
 }
@@ -59,19 +91,68 @@ impl Environment for CustomEnvironment {
     type ChainExtension = Iris;
 }
 
+/// the minimal interface a listing's price oracle must expose. The exchange resolves
+/// this with a raw `build_call` against the `quote` selector rather than a generated
+/// trait-ref, since this is the only message it ever needs to call on an oracle.
+pub trait PriceOracle {
+    fn quote(asset_id: u32) -> u64;
+}
+
 #[ink::contract(env = crate::CustomEnvironment)]
 mod iris_asset_exchange {
     // use ink_lang as ink;
     use super::IrisErr;
+    use ink_prelude::vec::Vec;
     use ink_storage::traits::SpreadAllocate;
 
+    /// a published sale's ask: either a static `price`, or a `PriceOracle` contract
+    /// (see `purchase_tokens`) that quotes the live price at purchase time
+    #[derive(Debug, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Listing {
+        pub price: u64,
+        pub oracle: Option<AccountId>,
+    }
+
+    /// the minting policy for a published sale: how many units may ever be sold under
+    /// it, how many have been sold so far, and the per-unit price
+    #[derive(Debug, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct SalePolicy {
+        /// the quantity originally minted to the contract when the sale was published;
+        /// fixed for the lifetime of the listing
+        pub amount_minted: u64,
+        pub max_supply: u64,
+        /// the number of units sold against `max_supply` so far; starts at `0` and is
+        /// only incremented once a purchase's lock and asset transfer have succeeded
+        pub units_sold: u64,
+        pub price_per_mint: Balance,
+    }
+
     /// Defines the storage of our contract.
     ///
     #[ink(storage)]
     #[derive(SpreadAllocate)]
     pub struct IrisAssetExchange {
-        /// maps the owner of a token sale to the asset id and asking price 
-        registry: ink_storage::Mapping<(AccountId, u32), u64>,
+        /// the account that deployed the exchange; the only account that can `terminate` it
+        owner: AccountId,
+        /// maps the owner of a token sale to the asset id and its listing
+        registry: ink_storage::Mapping<(AccountId, u32), Listing>,
+        /// the supply cap and running mint count for each published sale
+        sale_policies: ink_storage::Mapping<(AccountId, u32), SalePolicy>,
+        /// timelocked purchases awaiting seller payout, keyed by (buyer, asset_id) and
+        /// holding the (price, release_block) that was locked in `purchase_with_timelock`
+        escrows: ink_storage::Mapping<(AccountId, u32), (u64, BlockNumber)>,
+        /// every `(owner, asset_id)` with a listing currently in `registry`, since a
+        /// `Mapping` can't be iterated on its own; kept in sync by `publish_sale`,
+        /// `cancel_sale` and `terminate`
+        listings: Vec<(AccountId, u32)>,
     }
 
     #[ink(event)]
@@ -83,12 +164,17 @@ mod iris_asset_exchange {
     #[ink(event)]
     pub struct AssetNotRegistered { }
 
+    #[ink(event)]
+    pub struct SaleCancelled { }
+
     impl IrisAssetExchange {
 
         /// build a new  Iris Asset Exchange
         #[ink(constructor, payable)]
         pub fn new() -> Self {
-            ink_lang::utils::initialize_contract(|_| {})
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.owner = Self::env().caller();
+            })
         }
 
         /// Default constructor
@@ -98,59 +184,323 @@ mod iris_asset_exchange {
         }
 
         /// Provide pricing for a static amount of assets.
-        /// 
-        /// This function mints new assets from an asset class owned by the caller 
+        ///
+        /// This function mints new assets from an asset class owned by the caller
         /// and assigns them to the contract address. It adds an item to the exchange's registry,
         /// associating the asset id with the price determined by the caller.
-        /// 
+        ///
         /// * `asset_id`: An asset_id associated with an owned asset class
         /// * `amount`: The amount of assets that will be minted and provisioned to the exchange
-        /// * `price`: The price (in OBOL) of each token
-        /// 
+        /// * `price`: The price (in OBOL) of each token, used as-is unless `oracle` is set
+        /// * `max_supply`: The total number of units that may ever be sold under this listing
+        /// * `oracle`: An optional `PriceOracle` contract; when set, `purchase_tokens`
+        ///   resolves the live price from it instead of using `price` directly
+        ///
          #[ink(message)]
-         pub fn publish_sale(&mut self, asset_id: u32, amount: u64, price: u64) {
+         pub fn publish_sale(
+             &mut self,
+             asset_id: u32,
+             amount: u64,
+             price: u64,
+             max_supply: u64,
+             oracle: Option<AccountId>,
+         ) -> Result<(), IrisErr> {
              let caller = self.env().caller();
+             if self.sale_policies.contains((&caller, &asset_id)) {
+                 return Err(IrisErr::DuplicateListing);
+             }
              self.env()
                  .extension()
                  .mint(
                      caller, self.env().account_id(), asset_id, amount,
-                 ).map_err(|_| {});
-            self.registry.insert((&caller, &asset_id), &price);
+                 )?;
+            self.registry.insert((&caller, &asset_id), &Listing { price, oracle });
+            self.sale_policies.insert((&caller, &asset_id), &SalePolicy {
+                amount_minted: amount,
+                max_supply,
+                units_sold: 0,
+                price_per_mint: price as Balance,
+            });
+            self.listings.push((caller, asset_id));
              self.env().emit_event(AssetTransferSuccess { });
+             Ok(())
          }
 
+        /// reject a purchase that would push `units_sold` past the listing's
+        /// `max_supply`; doesn't mutate anything itself, since the purchase may still
+        /// fail after this check (see `record_sale`)
+        fn check_amount(&self, asset_id: u32, owner: AccountId, amount: u64) -> Result<(), IrisErr> {
+            let policy = self.sale_policies.get((&owner, &asset_id)).ok_or(IrisErr::ExceedsMaxSupply)?;
+            if policy.units_sold + amount > policy.max_supply {
+                return Err(IrisErr::ExceedsMaxSupply);
+            }
+            Ok(())
+        }
+
+        /// record `amount` additional units as sold against a listing's supply cap.
+        /// Only called once a purchase's lock and asset transfer have both succeeded:
+        /// an ink message that returns `Err` still persists storage writes made before
+        /// the error (only a trap reverts), so incrementing `units_sold` any earlier
+        /// would consume supply on a purchase that never actually completed.
+        fn record_sale(&mut self, asset_id: u32, owner: AccountId, amount: u64) {
+            if let Some(mut policy) = self.sale_policies.get((&owner, &asset_id)) {
+                policy.units_sold += amount;
+                self.sale_policies.insert((&owner, &asset_id), &policy);
+            }
+        }
+
+        /// assert that the caller's transferred value matches `amount * price`
+        fn check_value(&self, amount: u64, price: u64) -> Result<(), IrisErr> {
+            if self.env().transferred_value() != (amount as Balance) * (price as Balance) {
+                return Err(IrisErr::IncorrectPayment);
+            }
+            Ok(())
+        }
+
+        /// hand the caller's attached native value straight back to them. `check_value`
+        /// only requires the attached value as proof that the caller can cover
+        /// `amount * price`; the actual payment rail is the `lock`/`lock_until`
+        /// chain-extension calls, which move the Iris runtime's own currency. Holding
+        /// onto the attached value as well would charge the caller twice and strand it
+        /// in the contract's account, so it's refunded as soon as it's been checked.
+        fn refund_transferred_value(&self) -> Result<(), IrisErr> {
+            self.env()
+                .transfer(self.env().caller(), self.env().transferred_value())
+                .map_err(|_| IrisErr::RefundFailed)
+        }
+
+        /// resolve the live price from a listing's `PriceOracle` via a raw
+        /// cross-contract call against its `quote` selector
+        fn quote_price(&self, oracle: AccountId, asset_id: u32) -> Result<u64, IrisErr> {
+            ink_env::call::build_call::<crate::CustomEnvironment>()
+                .call_type(
+                    ink_env::call::Call::new(oracle)
+                        .gas_limit(0)
+                        .transferred_value(0),
+                )
+                .exec_input(
+                    ink_env::call::ExecutionInput::new(ink_env::call::Selector::new(
+                        ink_lang::selector_bytes!("quote"),
+                    ))
+                    .push_arg(asset_id),
+                )
+                .returns::<u64>()
+                .fire()
+                .map_err(|_| IrisErr::OracleQueryFailed)
+        }
+
         /// Purchase assets from the exchange.
-        /// 
+        ///
         /// This function performs the following process:
-        /// 1. lock price*amount tokens
-        /// 2. Transfer the asset from the contract account to the caller
-        /// 3. unlock the locked tokens from (1) and transfer to the owner of the asset class
-        /// 
+        /// 1. resolve the price, either the listing's static `price` or, when the
+        ///    listing has a `PriceOracle`, the oracle's current quote
+        /// 2. check the attached native value equals `amount * price`, then refund it
+        ///    (see `refund_transferred_value`): the real payment rail is step 3 below
+        /// 3. lock price*amount tokens
+        /// 4. Transfer the asset from the contract account to the caller
+        /// 5. unlock the locked tokens from (3) and transfer to the owner of the asset class
+        ///
         /// * `owner`: The owner of the asset class from which the asset to be purchased was minted
         /// * `asset_id`: The id of the owned asset class
         /// * `amount`: The amount of assets to purchase
-        /// 
-        #[ink(message)]
-        pub fn purchase_tokens(&mut self, owner: AccountId, asset_id: u32, amount: u64) {
+        ///
+        #[ink(message, payable)]
+        pub fn purchase_tokens(&mut self, owner: AccountId, asset_id: u32, amount: u64) -> Result<(), IrisErr> {
             let caller = self.env().caller();
             // calculate total cost
-            if let Some(price) = self.registry.get((&owner, &asset_id)) {
+            if let Some(listing) = self.registry.get((&owner, &asset_id)) {
+                let price = match listing.oracle {
+                    Some(oracle) => self.quote_price(oracle, asset_id)?,
+                    None => listing.price,
+                };
+                self.check_value(amount, price)?;
+                self.check_amount(asset_id, owner, amount)?;
+                self.refund_transferred_value()?;
                 let total_cost = amount * price;
                 // caller locks total_cost
-                self.env().extension().lock(total_cost).map_err(|_| {});
+                self.env().extension().lock(total_cost)?;
                 // contract grants tokens to caller
                 self.env()
                     .extension()
                     .transfer_asset(
                         self.env().account_id(), caller, asset_id, amount,
-                    ).map_err(|_| {});
+                    )?;
+                // the lock and transfer above both succeeded: this purchase is
+                // committed, so it's now safe to count its units as sold
+                self.record_sale(asset_id, owner, amount);
                 // caller send tokens to owner
-                self.env().extension().unlock_and_transfer(owner).map_err(|_| {});
+                self.env().extension().unlock_and_transfer(owner)?;
                 self.env().emit_event(AssetTransferSuccess { });
+                Ok(())
             } else {
                 self.env().emit_event(AssetNotRegistered { });
+                Ok(())
             }
         }
+
+        /// Purchase assets with a timelocked seller payout.
+        ///
+        /// Validates the transferred value and supply cap the same way as
+        /// `purchase_tokens` (and likewise refunds the attached native value once it's
+        /// checked), except that it always uses the listing's static `price` and never
+        /// queries a `PriceOracle`; locks `price*amount` tokens and transfers the asset to the caller
+        /// immediately, but defers the seller's payout: the locked currency is only
+        /// released once `lock_period` blocks have passed, via `claim_escrow`. This
+        /// gives the seller a dispute/cancellation window before funds settle.
+        ///
+        /// * `owner`: The owner of the asset class from which the asset was minted
+        /// * `asset_id`: The id of the owned asset class
+        /// * `amount`: The amount of assets to purchase
+        /// * `lock_period`: How many blocks to hold the payout in escrow for
+        ///
+        #[ink(message, payable)]
+        pub fn purchase_with_timelock(
+            &mut self,
+            owner: AccountId,
+            asset_id: u32,
+            amount: u64,
+            lock_period: BlockNumber,
+        ) -> Result<(), IrisErr> {
+            let caller = self.env().caller();
+            if let Some(listing) = self.registry.get((&owner, &asset_id)) {
+                let price = listing.price;
+                self.check_value(amount, price)?;
+                self.check_amount(asset_id, owner, amount)?;
+                self.refund_transferred_value()?;
+                let total_cost = amount * price;
+                let release_block = self.env().block_number() + lock_period;
+                self.env().extension().lock_until(total_cost, release_block)?;
+                self.env()
+                    .extension()
+                    .transfer_asset(
+                        self.env().account_id(), caller, asset_id, amount,
+                    )?;
+                self.record_sale(asset_id, owner, amount);
+                self.escrows.insert((&caller, &asset_id), &(price, release_block));
+                self.env().emit_event(AssetTransferSuccess { });
+                Ok(())
+            } else {
+                self.env().emit_event(AssetNotRegistered { });
+                Ok(())
+            }
+        }
+
+        /// Finalize a timelocked purchase, paying the locked currency out to `owner`
+        /// once the escrow's release block has been reached.
+        ///
+        /// * `buyer`: The account that called `purchase_with_timelock`
+        /// * `owner`: The owner of the asset class, and recipient of the payout
+        /// * `asset_id`: The id of the purchased asset class
+        ///
+        #[ink(message)]
+        pub fn claim_escrow(&mut self, buyer: AccountId, owner: AccountId, asset_id: u32) -> Result<(), IrisErr> {
+            if let Some((_price, release_block)) = self.escrows.get((&buyer, &asset_id)) {
+                if self.env().block_number() >= release_block {
+                    self.env().extension().unlock_and_transfer_timelocked(owner)?;
+                    self.escrows.remove((&buyer, &asset_id));
+                    self.env().emit_event(AssetTransferSuccess { });
+                }
+            } else {
+                self.env().emit_event(AssetNotRegistered { });
+            }
+            Ok(())
+        }
+
+        /// Withdraw a published listing.
+        ///
+        /// Removes the caller's `(caller, asset_id)` listing and transfers any unsold
+        /// units still held at the exchange's account back to the caller.
+        ///
+        /// * `asset_id`: The id of the listing to withdraw
+        ///
+        #[ink(message)]
+        pub fn cancel_sale(&mut self, asset_id: u32) -> Result<(), IrisErr> {
+            let caller = self.env().caller();
+            if let Some(policy) = self.sale_policies.get((&caller, &asset_id)) {
+                let unsold = Self::unsold_units(&policy);
+                self.registry.remove((&caller, &asset_id));
+                self.sale_policies.remove((&caller, &asset_id));
+                self.listings.retain(|listing| *listing != (caller, asset_id));
+                if unsold > 0 {
+                    self.env()
+                        .extension()
+                        .transfer_asset(
+                            self.env().account_id(), caller, asset_id, unsold,
+                        )?;
+                }
+                self.env().emit_event(SaleCancelled { });
+            } else {
+                self.env().emit_event(AssetNotRegistered { });
+            }
+            Ok(())
+        }
+
+        /// Wind the exchange down.
+        ///
+        /// Owner-only: returns the unsold units of each given listing (see
+        /// `cancel_sale`), releases any currency still held in escrow, and terminates
+        /// the contract, sending its remaining balance to `self.env().caller()`.
+        ///
+        /// * `listings`: the `(owner, asset_id)` listings to settle before terminating
+        ///
+        #[ink(message)]
+        pub fn terminate(&mut self, listings: ink_prelude::vec::Vec<(AccountId, u32)>) -> Result<(), IrisErr> {
+            let caller = self.env().caller();
+            if caller != self.owner {
+                return Err(IrisErr::NotOwner);
+            }
+            for (seller, asset_id) in listings {
+                if let Some(policy) = self.sale_policies.get((&seller, &asset_id)) {
+                    let unsold = Self::unsold_units(&policy);
+                    if unsold > 0 {
+                        self.env()
+                            .extension()
+                            .transfer_asset(
+                                self.env().account_id(), seller, asset_id, unsold,
+                            )?;
+                    }
+                    self.registry.remove((&seller, &asset_id));
+                    self.sale_policies.remove((&seller, &asset_id));
+                    self.listings.retain(|listing| *listing != (seller, asset_id));
+                }
+            }
+            // release any currency still held in a timelocked escrow
+            let _ = self.env().extension().unlock_and_transfer_timelocked(caller);
+            self.env().terminate_contract(caller);
+        }
+
+        /// the portion of a listing's originally-minted units that haven't been sold
+        fn unsold_units(policy: &SalePolicy) -> u64 {
+            policy.amount_minted.saturating_sub(policy.units_sold)
+        }
+
+        /// enumerate every active listing in the exchange, so a front-end or another
+        /// contract can discover the market without indexing every `publish_sale` event
+        #[ink(message)]
+        pub fn list_sales(&self) -> Vec<(AccountId, u32, u64)> {
+            self.listings
+                .iter()
+                .filter_map(|(owner, asset_id)| {
+                    self.registry
+                        .get((owner, asset_id))
+                        .map(|listing| (*owner, *asset_id, listing.price))
+                })
+                .collect()
+        }
+
+        /// enumerate the listings a given owner currently has published
+        #[ink(message)]
+        pub fn sales_by_owner(&self, owner: AccountId) -> Vec<(u32, u64)> {
+            self.listings
+                .iter()
+                .filter(|(listing_owner, _)| *listing_owner == owner)
+                .filter_map(|(listing_owner, asset_id)| {
+                    self.registry
+                        .get((listing_owner, asset_id))
+                        .map(|listing| (*asset_id, listing.price))
+                })
+                .collect()
+        }
     }
 
     /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
@@ -0,0 +1,96 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+//!
+//! Tlock Auction Factory
+//!
+//! Instantiates `TlockAuction` contracts on demand via ink!'s cross-contract
+//! instantiation, using a caller-supplied salt so the resulting auction's
+//! address is deterministic and can be computed off-chain before it exists.
+//!
+
+#[ink::contract]
+mod tlock_auction_factory {
+    use ink::storage::Mapping;
+    use ink::prelude::vec::Vec;
+    use tlock_auction::TlockAuctionRef;
+
+    #[ink(event)]
+    pub struct AuctionCreated {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        auction: AccountId,
+        salt: Hash,
+    }
+
+    #[ink(storage)]
+    pub struct TlockAuctionFactory {
+        /// the `TlockAuction` code hash to instantiate
+        auction_code_hash: Hash,
+        /// every auction deployed by an owner, keyed by the salt they chose
+        auctions: Mapping<(AccountId, Hash), AccountId>,
+        /// the salts an owner has used, so their auctions can be enumerated
+        salts_by_owner: Mapping<AccountId, Vec<Hash>>,
+    }
+
+    impl TlockAuctionFactory {
+        #[ink(constructor)]
+        pub fn new(auction_code_hash: Hash) -> Self {
+            Self {
+                auction_code_hash,
+                auctions: Mapping::default(),
+                salts_by_owner: Mapping::default(),
+            }
+        }
+
+        /// deterministically instantiate a new `TlockAuction` at the address derived
+        /// from `salt`, so a front-end can precompute the address before calling this.
+        /// Fails if an auction already exists at that `(caller, salt)`.
+        #[ink(message)]
+        pub fn create_auction(
+            &mut self,
+            salt: Hash,
+            name: Vec<u8>,
+            asset_id: u32,
+            amount: u8,
+            slot_ids: Vec<u32>,
+            threshold: u8,
+        ) -> AccountId {
+            let owner = self.env().caller();
+            if self.auctions.contains((owner, salt)) {
+                panic!("an auction already exists for this owner at this salt");
+            }
+
+            let auction = TlockAuctionRef::new(name, asset_id, amount, slot_ids, threshold)
+                .endowment(0)
+                .code_hash(self.auction_code_hash)
+                .salt_bytes(salt.as_ref())
+                .instantiate();
+            let auction_id = auction.to_account_id();
+
+            self.auctions.insert((owner, salt), &auction_id);
+            let mut salts = self.salts_by_owner.get(owner).unwrap_or_default();
+            salts.push(salt);
+            self.salts_by_owner.insert(owner, &salts);
+
+            self.env().emit_event(AuctionCreated { owner, auction: auction_id, salt });
+            auction_id
+        }
+
+        /// look up the address of the auction an owner deployed at a given salt
+        #[ink(message)]
+        pub fn auction_at(&self, owner: AccountId, salt: Hash) -> Option<AccountId> {
+            self.auctions.get((owner, salt))
+        }
+
+        /// enumerate every auction an owner has deployed through this factory
+        #[ink(message)]
+        pub fn auctions_of(&self, owner: AccountId) -> Vec<AccountId> {
+            self.salts_by_owner
+                .get(owner)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|salt| self.auctions.get((owner, salt)))
+                .collect()
+        }
+    }
+}
@@ -15,6 +15,14 @@ pub trait ETF {
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum EtfErr {
     FailCheckSlot,
+    /// fewer than `threshold` usable slot secrets were supplied to `complete`
+    InsufficientSecrets,
+    /// `propose` was called after the auction's last slot deadline has passed
+    ProposalWindowClosed,
+    /// `complete`/`bid` was called before the auction's last slot deadline has passed
+    AuctionStillOpen,
+    /// `propose`'s `capsule` didn't have one share per slot in `slot_ids`
+    CapsuleSizeMismatch,
 }
 
 impl ink_env::chain_extension::FromStatusCode for EtfErr {
@@ -49,6 +57,7 @@ mod tlock_auction {
     use super::EtfErr;
     use ink::storage::Mapping;
     use ink::prelude::vec::Vec;
+    use scale::Decode;
 
     use crypto::{
         client::client::{DefaultEtfClient, EtfClient},
@@ -67,17 +76,35 @@ mod tlock_auction {
         pub amount: u8,
     }
 
+    /// a decrypted, decoded proposal: a bidder's committed amount
+    #[derive(Debug, Clone, scale::Decode, scale::Encode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Bid {
+        pub bidder: AccountId,
+        pub amount: Balance,
+    }
+
     /// Defines the storage of your contract.
     /// Add new fields to the below struct in order
     /// to add new static storage fields to your contract.
     #[ink(storage)]
     pub struct TlockAuction {
+        /// the account that created the auction and receives the clearing price
+        owner: AccountId,
         auction_item: AuctionItem,
         /// the slot schedule for this contract
         slot_ids: Vec<u32>,
         threshold: u8,
         proposals: Mapping<AccountId, (Vec<u8>, Vec<u8>, Vec<Vec<u8>>)>, // ciphertext, nonce, capsule
-        // deposits: Mapping<AccountId, Balance>,
+        /// the conditional-escrow deposit each bidder locked when they proposed
+        deposits: Mapping<AccountId, Balance>,
+        /// tracks which participants' deposits have already been paid out, so that
+        /// settlement in `bid` is idempotent and a partially-failed payout can be
+        /// retried without double-spending
+        settled: Mapping<AccountId, bool>,
         /// ink mapping has no support for iteration so we need to loop over this vec to read through the proposals
         /// but maybe could do a struct instead? (acctid, vec, vec, vec)
         participants: Vec<AccountId>,
@@ -85,12 +112,44 @@ mod tlock_auction {
         // revealed_bids: Vec<Vec<u8>>,
         winners: Vec<AccountId>,
         revealed_bids: Vec<Vec<u8>>,
+        /// the clearing price the winner must pay (the second-highest valid bid)
+        clearing_price: Option<Balance>,
     }
 
-    impl TlockAuction {
+    /// a sealed bid was proposed; off-chain indexers can follow bidder participation
+    /// from this alone, without iterating `participants` through contract calls
+    #[ink(event)]
+    pub struct BidProposed {
+        #[ink(topic)]
+        pub bidder: AccountId,
+    }
+
+    /// the auction settled: `winners` and `clearing_price` are now set
+    #[ink(event)]
+    pub struct AuctionCompleted {
+        #[ink(topic)]
+        pub asset_id: u32,
+        pub clearing_price: Option<Balance>,
+    }
+
+    /// the winner's deposit was settled: the clearing price was paid to the owner
+    #[ink(event)]
+    pub struct WinnerSettled {
+        #[ink(topic)]
+        pub winner: AccountId,
+        pub amount: Balance,
+    }
 
-        // #[ink(event)]
-        // pub struct PublishedBid;
+    /// a participant's deposit was refunded (a loser's deposit in full, or the
+    /// winner's surplus above the clearing price)
+    #[ink(event)]
+    pub struct DepositRefunded {
+        #[ink(topic)]
+        pub bidder: AccountId,
+        pub amount: Balance,
+    }
+
+    impl TlockAuction {
 
         /// Constructor that initializes the `bool` value to the given `init_value`.
         #[ink(constructor, payable)]
@@ -103,18 +162,24 @@ mod tlock_auction {
         ) -> Self {
             let auction_item = AuctionItem { name, asset_id, amount };
             let proposals = Mapping::default();
+            let deposits = Mapping::default();
+            let settled = Mapping::default();
             let participants: Vec<AccountId> = Vec::new();
             let winners: Vec<AccountId> = Vec::new();
             let revealed_bids: Vec<Vec<u8>> = Vec::new();
             // check that they own the asset
             Self {
+                owner: Self::env().caller(),
                 auction_item,
                 slot_ids,
                 threshold,
                 proposals,
+                deposits,
+                settled,
                 participants,
                 winners,
                 revealed_bids,
+                clearing_price: None,
             }
         }
 
@@ -139,72 +204,180 @@ mod tlock_auction {
         // add your proposal
         // a proposal is a signed, timelocked tx that calls the 'bid' function of this contract
         #[ink(message, payable)]
-        pub fn propose(&mut self, ciphertext: Vec<u8>, nonce: Vec<u8>, capsule: Vec<Vec<u8>>) {
+        pub fn propose(&mut self, ciphertext: Vec<u8>, nonce: Vec<u8>, capsule: Vec<Vec<u8>>) -> Result<(), EtfErr> {
             let caller = self.env().caller();
-            // if after deadline then return an error
             // TODO: Should there be some validation on owner? this call will fail if the owner is incorrect anyway
             // check if the last slot has passed
             let is_past_deadline = self.env()
                 .extension()
                 .check_slot(self.slot_ids[self.slot_ids.len() - 1]);
             if is_past_deadline {
-                // STOP here, return error
+                return Err(EtfErr::ProposalWindowClosed);
+            }
+            // `complete` indexes `capsule[slot]` by the slots it selects for its quorum,
+            // one share per slot in `slot_ids`; reject a proposal that doesn't have that
+            // shape instead of letting it index out of bounds and brick settlement later
+            if capsule.len() != self.slot_ids.len() {
+                return Err(EtfErr::CapsuleSizeMismatch);
             }
             // 2. other checks? [no duplicates, block_list, allow_list]
-            // verify min deposit (later)
-            // let balance = Self::env().transferred_value();
-            // Self::env().transfer(to, balance)?;
+            // lock the transferred value as this bidder's conditional-escrow deposit;
+            // it is released in `bid` once the deadline condition is met
+            let deposit = self.env().transferred_value();
+            self.deposits.insert(caller, &deposit);
 
             if !self.participants.contains(&caller.clone()) {
                 self.participants.push(caller.clone());
             }
             self.proposals.insert(caller, &(ciphertext, nonce, capsule));
-            // let _ = self.env().transfer(self.env().account_id(), deposit);
-            // emit event here
-            // Self::env().emit_event(PublishedBid{});
+            self.env().emit_event(BidProposed { bidder: caller });
+            Ok(())
         }
 
+        /// settle the caller's conditional-escrow deposit once the auction's deadline
+        /// condition holds: the winner pays the clearing price to the auction owner and
+        /// reclaims their surplus, everyone else gets a full refund. Whether the caller
+        /// is the winner is decided entirely by `self.winners`, as recorded by
+        /// `complete` (the balance condition there already rejected any bidder whose
+        /// decrypted amount exceeded their deposit) — there is no caller-supplied input
+        /// to this message that could spoof the outcome. Settlement is recorded in
+        /// `settled` so a repeat call is a no-op rather than a double payout.
         #[ink(message)]
-        pub fn bid(&mut self, amount: Balance) {
+        pub fn bid(&mut self) -> Result<(), EtfErr> {
             let is_past_deadline = self.env()
                 .extension()
                 .check_slot(self.slot_ids[self.slot_ids.len() - 1]);
-            if is_past_deadline {
-                // if before the deadline, return an error
-                if self.winners.contains(&self.env().caller()) {
-                    // payout amount to owner
-                    // self.env().transfer(self.env().account_id(), amount);
-                    // owner transfers nft to winner
-                } else {
-                    // you lost, return deposit 
+            if !is_past_deadline {
+                // before the deadline there's nothing to settle yet
+                return Err(EtfErr::AuctionStillOpen);
+            }
+            let caller = self.env().caller();
+            if self.settled.get(caller).unwrap_or(false) {
+                // already settled: idempotent no-op, avoids a double-spend on retry
+                return Ok(());
+            }
+            let deposit = self.deposits.get(caller).unwrap_or_default();
+            if self.winners.contains(&caller) {
+                let clearing_price = self.clearing_price.unwrap_or_default();
+                let _ = self.env().transfer(self.owner, clearing_price);
+                self.env().emit_event(WinnerSettled { winner: caller, amount: clearing_price });
+                let surplus = deposit.saturating_sub(clearing_price);
+                if surplus > 0 {
+                    let _ = self.env().transfer(caller, surplus);
+                    self.env().emit_event(DepositRefunded { bidder: caller, amount: surplus });
                 }
-            } else {
-                // return error
+            } else if deposit > 0 {
+                // a loser, or a "winner" whose decrypted amount exceeds their deposit
+                // (disqualified) - their deposit is still refundable in full
+                let _ = self.env().transfer(caller, deposit);
+                self.env().emit_event(DepositRefunded { bidder: caller, amount: deposit });
             }
+            self.settled.insert(caller, &true);
+            Ok(())
         }
 
+        /// decrypt every proposal, decode it into a `Bid`, and settle the auction as a
+        /// sealed-bid second-price (Vickrey) auction: the highest valid bid wins and pays
+        /// the second-highest distinct valid amount, or its own amount when no lower
+        /// valid bid exists (there is no reserve price). Bids that fail to decode, or whose embedded `bidder` does
+        /// not match the `participants` entry they were stored under, are discarded.
+        ///
+        /// `secrets` is positional with `slot_ids`: `secrets[i]` is the IBE secret for
+        /// `slot_ids[i]`, or an empty `Vec` if that slot hasn't been authored yet. Rather
+        /// than waiting on every slot, settlement proceeds as soon as a `threshold`-sized
+        /// quorum of secrets is usable; fewer than that returns `InsufficientSecrets`.
         #[ink(message)]
-        pub fn complete(&mut self, pp: Vec<u8>, secrets: Vec<Vec<u8>>) {
+        pub fn complete(&mut self, pp: Vec<u8>, secrets: Vec<Vec<u8>>) -> Result<(), EtfErr> {
             let is_past_deadline = self.env()
                 .extension()
                 .check_slot(self.slot_ids[self.slot_ids.len() - 1]);
             if !is_past_deadline {
-                // STOP here, return error
+                return Err(EtfErr::AuctionStillOpen);
             }
-            // 1. ensure past deadline
+
+            // honor `threshold`: settle as soon as a quorum of slot secrets is usable,
+            // rather than requiring every slot to have produced one. `decrypt` pairs
+            // capsule shares with secrets positionally, so we carry the slot index
+            // through the filter and select the matching capsule share alongside each
+            // secret, rather than just slicing the leading `threshold` secrets.
+            let quorum_slots: Vec<usize> = secrets
+                .iter()
+                .enumerate()
+                .filter(|(_slot, secret)| !secret.is_empty())
+                .take(self.threshold as usize)
+                .map(|(slot, _secret)| slot)
+                .collect();
+            if quorum_slots.len() < self.threshold as usize {
+                return Err(EtfErr::InsufficientSecrets);
+            }
+            let quorum: Vec<Vec<u8>> = quorum_slots.iter().map(|&slot| secrets[slot].clone()).collect();
+
+            let mut valid_bids: Vec<Bid> = Vec::new();
             self.participants.iter().for_each(|p| {
-                self.proposals.get(&p).iter().for_each(|proposal| {
-                    let signed_tx = DefaultEtfClient::<BfIbe>::decrypt(
-                        pp.clone(), proposal.0.clone(), 
-                        proposal.1.clone(), proposal.2.clone(), 
-                        secrets.clone(),
-                    ).unwrap();
-                    // need to decode the tx and get the amount and use it to identify the winner
-                    // 1. decode (how?!) + verify
-                    // 2. check if winner
-                    self.revealed_bids.push(signed_tx);
+                self.proposals.get(p).iter().for_each(|proposal| {
+                    let capsule_quorum: Vec<Vec<u8>> = quorum_slots
+                        .iter()
+                        .map(|&slot| proposal.2[slot].clone())
+                        .collect();
+                    let decrypted = DefaultEtfClient::<BfIbe>::decrypt(
+                        pp.clone(), proposal.0.clone(),
+                        proposal.1.clone(), capsule_quorum,
+                        quorum.clone(),
+                    );
+                    // a garbage or malformed proposal shouldn't be able to brick
+                    // settlement for every other participant; skip it instead
+                    let signed_tx = match decrypted {
+                        Ok(signed_tx) => signed_tx,
+                        Err(_) => return,
+                    };
+                    self.revealed_bids.push(signed_tx.clone());
+                    match Bid::decode(&mut signed_tx.as_slice()) {
+                        Ok(bid) if bid.bidder == *p => {
+                            let deposit = self.deposits.get(p).unwrap_or_default();
+                            // the balance condition: a bidder whose decrypted amount
+                            // exceeds their locked deposit is disqualified from winning
+                            // (their deposit remains refundable via `bid`)
+                            if deposit >= bid.amount {
+                                valid_bids.push(bid);
+                            }
+                        },
+                        _ => {
+                            // failed to decode, or the embedded bidder doesn't match
+                            // the participant this proposal was stored under
+                        },
+                    }
                 });
             });
+
+            // 2. find the highest bid (the winner) and the second-highest distinct
+            // amount (the clearing price), breaking exact ties deterministically by
+            // `AccountId` ordering.
+            let mut ranked = valid_bids.clone();
+            ranked.sort_by(|a, b| {
+                b.amount.cmp(&a.amount).then_with(|| a.bidder.cmp(&b.bidder))
+            });
+
+            match ranked.split_first() {
+                None => {
+                    // zero valid bids: the auction is voided
+                    self.winners = Vec::new();
+                    self.clearing_price = None;
+                },
+                Some((winner, rest)) => {
+                    let clearing_price = rest
+                        .iter()
+                        .find(|bid| bid.amount < winner.amount)
+                        .map(|bid| bid.amount)
+                        .unwrap_or(winner.amount);
+                    self.winners = vec![winner.bidder];
+                    self.clearing_price = Some(clearing_price);
+                },
+            }
+            self.env().emit_event(AuctionCompleted {
+                asset_id: self.auction_item.asset_id,
+                clearing_price: self.clearing_price,
+            });
+            Ok(())
         }
     }
 
@@ -229,13 +402,14 @@ mod tlock_auction {
             let threshold = 2;
             // we'll pretend that the blockchain is seeded with these params
             let ibe_params = test_ibe_params();
-            let seed_hash = crypto::utils::sha256(&crypto::utils::sha256(b"test0"));
-            let rng = ChaCha20Rng::from_seed(seed_hash.try_into().expect("should be 32 bytes; qed"));
+            let rng = seeded_rng(b"test0");
             // setup the auction contract
             let mut auction = TlockAuction::new(b"test1".to_vec(), 1u32, 1u8, slot_ids.clone(), threshold);
+            let accounts = ink_env::test::default_accounts::<crate::CustomEnvironment>();
 
-            let res = add_bid(slot_ids, threshold, ibe_params.0, ibe_params.1, rng);
-            auction.propose(res.0.clone(), res.1.clone(), res.2.clone());
+            let bid = Bid { bidder: accounts.alice, amount: 100 };
+            let res = add_bid(slot_ids, threshold, ibe_params.0, ibe_params.1, rng, bid);
+            auction.propose(res.0.clone(), res.1.clone(), res.2.clone()).unwrap();
 
             let participants = auction.participants;
             assert_eq!(participants.clone().len(), 1);
@@ -245,39 +419,187 @@ mod tlock_auction {
         }
 
         #[ink::test]
-        fn can_complete_auction() {
+        fn can_complete_auction_picks_winner_and_second_price_clearing() {
             let slot_ids = vec![vec![1,2,3], vec![2,3,4], vec![3,4,5]];
             let threshold = 2;
-            // we'll pretend that the blockchain is seeded with these params
             let ibe_params = test_ibe_params();
-            let seed_hash = crypto::utils::sha256(&crypto::utils::sha256(b"test1"));
-            let rng = ChaCha20Rng::from_seed(seed_hash.try_into().expect("should be 32 bytes; qed"));
-            // setup auction
+            let accounts = ink_env::test::default_accounts::<crate::CustomEnvironment>();
             let mut auction = TlockAuction::new(b"test1".to_vec(), 1u32, 1u8, slot_ids.clone(), threshold);
-            let res = add_bid(slot_ids.clone(), threshold, ibe_params.0.clone(), ibe_params.1, rng);
-            auction.propose(res.0.clone(), res.1.clone(), res.2.clone());
-            // prepare IBE slot secrets
-            // in practice this would be fetched from block headers
+
+            // alice bids 100 with a matching deposit, bob bids 60 with a matching deposit
+            propose_bid(
+                &mut auction, accounts.alice, 100, slot_ids.clone(), threshold,
+                ibe_params.0.clone(), ibe_params.1.clone(), seeded_rng(b"alice-100"),
+                Bid { bidder: accounts.alice, amount: 100 },
+            );
+            propose_bid(
+                &mut auction, accounts.bob, 60, slot_ids.clone(), threshold,
+                ibe_params.0.clone(), ibe_params.1.clone(), seeded_rng(b"bob-60"),
+                Bid { bidder: accounts.bob, amount: 60 },
+            );
+
             let ibe_slot_secrets: Vec<Vec<u8>> = ibe_extract(ibe_params.2, slot_ids).into_iter()
                 .map(|(sk, _)| sk).collect::<Vec<_>>();
-            // complete the auction
-            auction.complete(ibe_params.0, ibe_slot_secrets);
+            auction.complete(ibe_params.0, ibe_slot_secrets).unwrap();
+
+            assert_eq!(auction.revealed_bids.len(), 2);
+            assert_eq!(auction.winners, vec![accounts.alice]);
+            assert_eq!(auction.clearing_price, Some(60));
+        }
+
+        #[ink::test]
+        fn single_valid_bid_clears_at_its_own_amount() {
+            let slot_ids = vec![vec![1,2,3], vec![2,3,4], vec![3,4,5]];
+            let threshold = 2;
+            let ibe_params = test_ibe_params();
+            let accounts = ink_env::test::default_accounts::<crate::CustomEnvironment>();
+            let mut auction = TlockAuction::new(b"test1".to_vec(), 1u32, 1u8, slot_ids.clone(), threshold);
+
+            propose_bid(
+                &mut auction, accounts.alice, 100, slot_ids.clone(), threshold,
+                ibe_params.0.clone(), ibe_params.1.clone(), seeded_rng(b"alice-only"),
+                Bid { bidder: accounts.alice, amount: 100 },
+            );
+
+            let ibe_slot_secrets: Vec<Vec<u8>> = ibe_extract(ibe_params.2, slot_ids).into_iter()
+                .map(|(sk, _)| sk).collect::<Vec<_>>();
+            auction.complete(ibe_params.0, ibe_slot_secrets).unwrap();
+
+            assert_eq!(auction.winners, vec![accounts.alice]);
+            assert_eq!(auction.clearing_price, Some(100));
+        }
 
-            let revealed_bids = auction.revealed_bids;
-            assert_eq!(revealed_bids.len(), 1);
-            assert_eq!(revealed_bids[0], b"{I want to bid X tokens for your NFT}".to_vec());
+        #[ink::test]
+        fn tied_bids_break_tie_by_account_id() {
+            let slot_ids = vec![vec![1,2,3], vec![2,3,4], vec![3,4,5]];
+            let threshold = 2;
+            let ibe_params = test_ibe_params();
+            let accounts = ink_env::test::default_accounts::<crate::CustomEnvironment>();
+            let mut auction = TlockAuction::new(b"test1".to_vec(), 1u32, 1u8, slot_ids.clone(), threshold);
+
+            propose_bid(
+                &mut auction, accounts.alice, 100, slot_ids.clone(), threshold,
+                ibe_params.0.clone(), ibe_params.1.clone(), seeded_rng(b"alice-tie"),
+                Bid { bidder: accounts.alice, amount: 100 },
+            );
+            propose_bid(
+                &mut auction, accounts.bob, 100, slot_ids.clone(), threshold,
+                ibe_params.0.clone(), ibe_params.1.clone(), seeded_rng(b"bob-tie"),
+                Bid { bidder: accounts.bob, amount: 100 },
+            );
+
+            let ibe_slot_secrets: Vec<Vec<u8>> = ibe_extract(ibe_params.2, slot_ids).into_iter()
+                .map(|(sk, _)| sk).collect::<Vec<_>>();
+            auction.complete(ibe_params.0, ibe_slot_secrets).unwrap();
+
+            // equal amounts: the tie is broken deterministically by AccountId ordering
+            let expected_winner = accounts.alice.min(accounts.bob);
+            assert_eq!(auction.winners, vec![expected_winner]);
+            assert_eq!(auction.clearing_price, Some(100));
+        }
+
+        #[ink::test]
+        fn no_valid_bids_voids_the_auction() {
+            let slot_ids = vec![vec![1,2,3], vec![2,3,4], vec![3,4,5]];
+            let threshold = 2;
+            let ibe_params = test_ibe_params();
+            let accounts = ink_env::test::default_accounts::<crate::CustomEnvironment>();
+            let mut auction = TlockAuction::new(b"test1".to_vec(), 1u32, 1u8, slot_ids.clone(), threshold);
+
+            // alice's decrypted bid (100) exceeds her locked deposit (10), so the balance
+            // condition disqualifies the only proposal and no bid is valid
+            propose_bid(
+                &mut auction, accounts.alice, 10, slot_ids.clone(), threshold,
+                ibe_params.0.clone(), ibe_params.1.clone(), seeded_rng(b"alice-underfunded"),
+                Bid { bidder: accounts.alice, amount: 100 },
+            );
+
+            let ibe_slot_secrets: Vec<Vec<u8>> = ibe_extract(ibe_params.2, slot_ids).into_iter()
+                .map(|(sk, _)| sk).collect::<Vec<_>>();
+            auction.complete(ibe_params.0, ibe_slot_secrets).unwrap();
+
+            assert_eq!(auction.revealed_bids.len(), 1);
+            assert!(auction.winners.is_empty());
+            assert_eq!(auction.clearing_price, None);
+        }
+
+        #[ink::test]
+        fn propose_rejects_a_capsule_with_the_wrong_number_of_shares() {
+            let slot_ids = vec![vec![1,2,3], vec![2,3,4], vec![3,4,5]];
+            let threshold = 2;
+            let mut auction = TlockAuction::new(b"test1".to_vec(), 1u32, 1u8, slot_ids, threshold);
+
+            let res = auction.propose(b"ciphertext".to_vec(), b"nonce".to_vec(), vec![b"only-one-share".to_vec()]);
+
+            assert_eq!(res, Err(EtfErr::CapsuleSizeMismatch));
+        }
+
+        #[ink::test]
+        fn complete_skips_a_proposal_that_fails_to_decrypt() {
+            let slot_ids = vec![vec![1,2,3], vec![2,3,4], vec![3,4,5]];
+            let threshold = 2;
+            let ibe_params = test_ibe_params();
+            let accounts = ink_env::test::default_accounts::<crate::CustomEnvironment>();
+            let mut auction = TlockAuction::new(b"test1".to_vec(), 1u32, 1u8, slot_ids.clone(), threshold);
+
+            // bob's proposal is garbage (not produced by `DefaultEtfClient::encrypt`), so
+            // decryption will fail for it; it must not stop alice's valid bid from winning
+            ink_env::test::set_caller::<crate::CustomEnvironment>(accounts.bob);
+            ink_env::test::set_value_transferred::<crate::CustomEnvironment>(100);
+            auction.propose(
+                b"not-a-real-ciphertext".to_vec(),
+                b"not-a-real-nonce".to_vec(),
+                slot_ids.iter().map(|_| b"not-a-real-share".to_vec()).collect(),
+            ).unwrap();
+            propose_bid(
+                &mut auction, accounts.alice, 100, slot_ids.clone(), threshold,
+                ibe_params.0.clone(), ibe_params.1.clone(), seeded_rng(b"alice-vs-garbage"),
+                Bid { bidder: accounts.alice, amount: 100 },
+            );
+
+            let ibe_slot_secrets: Vec<Vec<u8>> = ibe_extract(ibe_params.2, slot_ids).into_iter()
+                .map(|(sk, _)| sk).collect::<Vec<_>>();
+            auction.complete(ibe_params.0, ibe_slot_secrets).unwrap();
+
+            assert_eq!(auction.revealed_bids.len(), 1);
+            assert_eq!(auction.winners, vec![accounts.alice]);
+            assert_eq!(auction.clearing_price, Some(100));
+        }
+
+        fn seeded_rng(seed: &[u8]) -> ChaCha20Rng {
+            let seed_hash = crypto::utils::sha256(&crypto::utils::sha256(seed));
+            ChaCha20Rng::from_seed(seed_hash.try_into().expect("should be 32 bytes; qed"))
+        }
+
+        /// set `caller` as the message sender with `deposit` as the transferred value,
+        /// encrypt a SCALE-encoded `bid` as a timelocked proposal, and submit it
+        fn propose_bid(
+            auction: &mut TlockAuction,
+            caller: AccountId,
+            deposit: Balance,
+            slot_ids: Vec<Vec<u8>>,
+            threshold: u8,
+            p: Vec<u8>, q: Vec<u8>,
+            rng: ChaCha20Rng,
+            bid: Bid,
+        ) {
+            ink_env::test::set_caller::<crate::CustomEnvironment>(caller);
+            ink_env::test::set_value_transferred::<crate::CustomEnvironment>(deposit);
+            let (ciphertext, nonce, capsule) = add_bid(slot_ids, threshold, p, q, rng, bid);
+            auction.propose(ciphertext, nonce, capsule).unwrap();
         }
 
         fn add_bid(
                 slot_ids: Vec<Vec<u8>>,
                 threshold: u8,
-                p: Vec<u8>, q: Vec<u8>, 
-                rng: ChaCha20Rng
+                p: Vec<u8>, q: Vec<u8>,
+                rng: ChaCha20Rng,
+                bid: Bid,
             ) -> (Vec<u8>, Vec<u8>, Vec<Vec<u8>>) {
-            let mock_bid_tx = b"{I want to bid X tokens for your NFT}".to_vec();
-            let res = 
+            let encoded_bid = scale::Encode::encode(&bid);
+            let res =
                 DefaultEtfClient::<BfIbe>::encrypt(
-                    p, q, &mock_bid_tx, slot_ids, threshold, rng
+                    p, q, &encoded_bid, slot_ids, threshold, rng
                 ).unwrap();
             (
                 res.aes_ct.ciphertext.clone(),